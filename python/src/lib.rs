@@ -2,11 +2,19 @@ mod coord_type;
 mod kdtree;
 mod rtree;
 
-use pyo3::exceptions::PyRuntimeWarning;
+use pyo3::exceptions::{PyRuntimeWarning, PyValueError};
 use pyo3::intern;
 use pyo3::prelude::*;
 use pyo3::types::PyTuple;
 
+use geo_index::kdtree::KDTreeBuilder;
+
+#[cfg(feature = "mmap")]
+use std::path::PathBuf;
+
+#[cfg(feature = "mmap")]
+use geo_index::kdtree::MmapKDTree;
+
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[pyfunction]
@@ -30,6 +38,102 @@ fn check_debug_build(py: Python) -> PyResult<()> {
     Ok(())
 }
 
+/// Builds a kdbush-formatted buffer from points added one at a time, optionally recording a
+/// bounds/CRS extension trailer.
+#[pyclass(name = "KDTreeBuilder", module = "geoindex_rs")]
+struct PyKDTreeBuilder(Option<KDTreeBuilder<f64>>);
+
+#[pymethods]
+impl PyKDTreeBuilder {
+    #[new]
+    #[pyo3(signature = (node_size=None))]
+    fn new(node_size: Option<u16>) -> PyResult<Self> {
+        if let Some(node_size) = node_size {
+            if !(2..=65535).contains(&node_size) {
+                return Err(PyValueError::new_err(
+                    "node_size must be between 2 and 65535",
+                ));
+            }
+        }
+
+        let builder = KDTreeBuilder::new();
+        let builder = match node_size {
+            Some(node_size) => builder.with_node_size(node_size),
+            None => builder,
+        };
+        Ok(Self(Some(builder)))
+    }
+
+    /// Add a point, returning the index it will have in the finished tree.
+    fn add(&mut self, x: f64, y: f64) -> PyResult<u32> {
+        Ok(self
+            .0
+            .as_mut()
+            .ok_or_else(|| PyValueError::new_err("builder already finished"))?
+            .add(x, y))
+    }
+
+    /// Record the bounding box (min x, min y, max x, max y) and, optionally, the CRS/EPSG code
+    /// to write into the finished buffer's extension trailer.
+    #[pyo3(signature = (bounds, crs=None))]
+    fn set_extension(&mut self, bounds: [f64; 4], crs: Option<u32>) -> PyResult<()> {
+        self.0
+            .as_mut()
+            .ok_or_else(|| PyValueError::new_err("builder already finished"))?
+            .set_extension(bounds, crs)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    /// Consume the builder, sorting the accumulated points into their kdbush layout and
+    /// returning the finished buffer's raw bytes.
+    fn finish(&mut self) -> PyResult<Vec<u8>> {
+        let owned = self
+            .0
+            .take()
+            .ok_or_else(|| PyValueError::new_err("builder already finished"))?
+            .finish()
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        Ok(owned.into_inner())
+    }
+}
+
+/// A read-only, memory-mapped KDTree, lazily queried from a file on disk.
+#[cfg(feature = "mmap")]
+#[pyclass(name = "MmapKDTree", module = "geoindex_rs")]
+struct PyMmapKDTree(MmapKDTree<f64>);
+
+#[cfg(feature = "mmap")]
+#[pymethods]
+impl PyMmapKDTree {
+    /// The bounding box (min x, min y, max x, max y) recorded in this buffer's extension
+    /// trailer, if it has one.
+    #[getter]
+    fn bounds(&self) -> Option<[f64; 4]> {
+        self.0.as_kdtree_ref().bounds()
+    }
+
+    /// The CRS/EPSG code recorded in this buffer's extension trailer, if it has one.
+    #[getter]
+    fn crs(&self) -> Option<u32> {
+        self.0.as_kdtree_ref().crs()
+    }
+
+    /// Find the indices of all points within the given bounding box (inclusive on every side),
+    /// querying the mapped buffer lazily without copying its coords or indices into memory.
+    fn range(&self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Vec<u32> {
+        self.0.as_kdtree_ref().range(min_x, min_y, max_x, max_y)
+    }
+}
+
+/// Open a kdbush-formatted file as a read-only memory map and lazily query it, without copying
+/// its coords and indices arrays into memory.
+#[cfg(feature = "mmap")]
+#[pyfunction]
+fn open_mmap(path: PathBuf) -> PyResult<PyMmapKDTree> {
+    let tree = MmapKDTree::open(path).map_err(|err| PyValueError::new_err(err.to_string()))?;
+    Ok(PyMmapKDTree(tree))
+}
+
 #[pymodule]
 fn _rust(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     check_debug_build(py)?;
@@ -38,6 +142,12 @@ fn _rust(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
 
     rtree::register_rtree_module(py, m, "geoindex_rs")?;
     kdtree::register_kdtree_module(py, m, "geoindex_rs")?;
+    m.add_class::<PyKDTreeBuilder>()?;
+    #[cfg(feature = "mmap")]
+    {
+        m.add_class::<PyMmapKDTree>()?;
+        m.add_wrapped(wrap_pyfunction!(open_mmap))?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,246 @@
+use crate::error::{GeoIndexError, Result};
+use crate::indices::Indices;
+use crate::kdtree::index::{KDTreeExtension, OwnedKDTree};
+use crate::r#type::IndexableNum;
+
+/// Default node size used by a [`KDTreeBuilder`] when none is explicitly requested, matching the
+/// JavaScript `kdbush` library's default since both target the same wire format.
+const DEFAULT_NODE_SIZE: u16 = 64;
+
+/// Builds an [`OwnedKDTree`] from raw points, computing the kd-tree ordering `kdbush` expects.
+///
+/// Most callers should go through this rather than
+/// [`OwnedKDTree::from_sorted_parts`][super::index::OwnedKDTree] directly, since it accepts
+/// points in arbitrary insertion order and handles sorting them into the node-size-bounded
+/// layout the wire format requires.
+#[derive(Debug)]
+pub struct KDTreeBuilder<N: IndexableNum> {
+    coords: Vec<N>,
+    node_size: u16,
+    extension: Option<KDTreeExtension>,
+}
+
+impl<N: IndexableNum> Default for KDTreeBuilder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: IndexableNum> KDTreeBuilder<N> {
+    /// Construct a new, empty builder with the default node size and no extension trailer.
+    pub fn new() -> Self {
+        Self {
+            coords: Vec::new(),
+            node_size: DEFAULT_NODE_SIZE,
+            extension: None,
+        }
+    }
+
+    /// Use a custom node size instead of the default.
+    pub fn with_node_size(mut self, node_size: u16) -> Self {
+        assert!((2..=65535).contains(&node_size));
+        self.node_size = node_size;
+        self
+    }
+
+    /// Record the bounding box (min x, min y, max x, max y) and, optionally, the CRS/EPSG code to
+    /// write into the finished buffer's extension trailer.
+    ///
+    /// `crs` must not be `Some(0)`: `0` is the wire encoding for "no CRS" in the extension
+    /// trailer, so a caller-supplied `0` would silently come back as `None` after a round trip
+    /// through bytes.
+    pub fn set_extension(&mut self, bounds: [f64; 4], crs: Option<u32>) -> Result<()> {
+        if crs == Some(0) {
+            return Err(GeoIndexError::General(
+                "crs must not be 0: that value is reserved to mean \"no CRS\" on the wire; pass \
+                 None instead."
+                    .to_string(),
+            ));
+        }
+        self.extension = Some(KDTreeExtension { bounds, crs });
+        Ok(())
+    }
+
+    /// Add a point, returning the index it will have in the finished tree.
+    pub fn add(&mut self, x: N, y: N) -> u32 {
+        let id = (self.coords.len() / 2) as u32;
+        self.coords.push(x);
+        self.coords.push(y);
+        id
+    }
+
+    /// The number of points added so far.
+    pub fn len(&self) -> usize {
+        self.coords.len() / 2
+    }
+
+    /// Whether any points have been added yet.
+    pub fn is_empty(&self) -> bool {
+        self.coords.is_empty()
+    }
+
+    /// Consume the builder, sorting the accumulated points into kdbush's node-size-bounded
+    /// layout and serializing the result, including the extension trailer if one was set.
+    pub fn finish(self) -> Result<OwnedKDTree<N>>
+    where
+        N: PartialOrd,
+    {
+        let num_items = self.len();
+        let mut order: Vec<u32> = (0..num_items as u32).collect();
+        sort_kd(&mut order, &self.coords, self.node_size as usize, 0);
+
+        let mut sorted_coords = Vec::with_capacity(self.coords.len());
+        for &id in &order {
+            sorted_coords.push(self.coords[2 * id as usize]);
+            sorted_coords.push(self.coords[2 * id as usize + 1]);
+        }
+
+        if num_items < 65536 {
+            let indices: Vec<u16> = order.iter().map(|&id| id as u16).collect();
+            OwnedKDTree::from_sorted_parts(
+                &sorted_coords,
+                Indices::U16(&indices),
+                self.node_size,
+                self.extension,
+            )
+        } else {
+            OwnedKDTree::from_sorted_parts(
+                &sorted_coords,
+                Indices::U32(&order),
+                self.node_size,
+                self.extension,
+            )
+        }
+    }
+}
+
+/// Recursively partition `ids` (a permutation of point indices into `coords`) into groups of at
+/// most `node_size`, alternating the split axis (0 = x, 1 = y) at each level, the same layout
+/// [`KDTreeRef::range`][crate::kdtree::KDTreeRef] expects to search.
+fn sort_kd<N: IndexableNum + PartialOrd>(
+    ids: &mut [u32],
+    coords: &[N],
+    node_size: usize,
+    axis: usize,
+) {
+    if ids.len() <= node_size {
+        return;
+    }
+
+    // Must match `KDTreeRef::range`'s `m = (left + right) / 2` over the same global span, or
+    // the query side prunes around a point the builder never actually partitioned on.
+    let mid = (ids.len() - 1) / 2;
+    // `unwrap_or(Ordering::Equal)` rather than `unwrap()`: a NaN coordinate makes `partial_cmp`
+    // return `None`, and this runs behind a plain `PartialOrd` bound with untrusted input
+    // (including from Python), so it must not panic across the FFI boundary.
+    if axis == 0 {
+        ids.select_nth_unstable_by(mid, |&a, &b| {
+            coords[2 * a as usize]
+                .partial_cmp(&coords[2 * b as usize])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    } else {
+        ids.select_nth_unstable_by(mid, |&a, &b| {
+            coords[2 * a as usize + 1]
+                .partial_cmp(&coords[2 * b as usize + 1])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    let (left, right) = ids.split_at_mut(mid);
+    sort_kd(left, coords, node_size, 1 - axis);
+    sort_kd(&mut right[1..], coords, node_size, 1 - axis);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kdtree::index::KDTreeRef;
+
+    #[test]
+    fn finish_round_trips_every_added_point() {
+        let mut builder = KDTreeBuilder::<f64>::new().with_node_size(2);
+        let points = [(0.0, 0.0), (5.0, 5.0), (1.0, 1.0), (4.0, 0.0), (0.0, 4.0)];
+        for &(x, y) in &points {
+            builder.add(x, y);
+        }
+
+        let owned = builder.finish().unwrap();
+        let tree_ref = KDTreeRef::<f64>::try_new(&owned).unwrap();
+
+        let mut found = tree_ref.range(0.0, 0.0, 5.0, 5.0);
+        found.sort_unstable();
+        assert_eq!(found, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn finish_writes_the_extension_trailer() {
+        let mut builder = KDTreeBuilder::<f64>::new();
+        builder
+            .set_extension([0.0, 0.0, 1.0, 1.0], Some(4326))
+            .unwrap();
+        builder.add(0.0, 0.0);
+        builder.add(1.0, 1.0);
+
+        let owned = builder.finish().unwrap();
+        assert_eq!(owned.bounds(), Some([0.0, 0.0, 1.0, 1.0]));
+        assert_eq!(owned.crs(), Some(4326));
+    }
+
+    #[test]
+    fn set_extension_rejects_crs_zero() {
+        let mut builder = KDTreeBuilder::<f64>::new();
+        let err = builder
+            .set_extension([0.0, 0.0, 1.0, 1.0], Some(0))
+            .unwrap_err();
+        assert!(matches!(err, GeoIndexError::General(_)));
+    }
+
+    #[test]
+    fn finish_range_finds_points_pruned_by_an_even_sized_group() {
+        // Covers an even-sized group, where `sort_kd`'s pivot must be `(ids.len() - 1) / 2` to
+        // match `KDTreeRef::range`'s global `(left + right) / 2`: with node_size 2 the top-level
+        // group here has 6 points, so that pivot is 2, while a local `ids.len() / 2` would be 3.
+        let mut builder = KDTreeBuilder::<f64>::new().with_node_size(2);
+        let points = [
+            (3.0, 4.0),
+            (9.0, 0.0),
+            (3.0, 1.0),
+            (2.0, 3.0),
+            (5.0, 8.0),
+            (4.0, 2.0),
+        ];
+        for &(x, y) in &points {
+            builder.add(x, y);
+        }
+
+        let owned = builder.finish().unwrap();
+        let tree_ref = KDTreeRef::<f64>::try_new(&owned).unwrap();
+
+        let mut found = tree_ref.range(2.0, 1.0, 5.0, 5.0);
+        found.sort_unstable();
+        assert_eq!(found, vec![0, 2, 3, 5]);
+    }
+
+    #[test]
+    fn finish_without_an_extension_round_trips_to_none() {
+        let mut builder = KDTreeBuilder::<f64>::new();
+        builder.add(0.0, 0.0);
+
+        let owned = builder.finish().unwrap();
+        assert_eq!(owned.bounds(), None);
+        assert_eq!(owned.crs(), None);
+    }
+
+    #[test]
+    fn finish_does_not_panic_on_a_nan_coordinate() {
+        // `partial_cmp` returns `None` for NaN; `sort_kd` must tolerate that instead of
+        // unwrapping it, since points come from arbitrary (including Python-supplied) input.
+        let mut builder = KDTreeBuilder::<f64>::new().with_node_size(2);
+        builder.add(f64::NAN, 0.0);
+        builder.add(1.0, 1.0);
+        builder.add(2.0, 2.0);
+
+        assert!(builder.finish().is_ok());
+    }
+}
@@ -1,12 +1,90 @@
+use std::cmp::Ordering;
 use std::marker::PhantomData;
 
-use bytemuck::cast_slice;
+use bytemuck::{cast_slice, try_cast_slice};
 
 use crate::error::{GeoIndexError, Result};
 use crate::indices::Indices;
 use crate::kdtree::constants::{KDBUSH_HEADER_SIZE, KDBUSH_MAGIC, KDBUSH_VERSION};
 use crate::r#type::IndexableNum;
 
+/// Bit within the version/type header byte that marks the presence of the extension trailer
+/// described in [`KDTreeMetadata::bounds`] and [`KDTreeMetadata::crs`].
+///
+/// The version nibble only ever encodes small values (currently just [`KDBUSH_VERSION`]), so its
+/// top bit is otherwise unused and safe to repurpose as a flag.
+const HAS_EXTENSION_FLAG: u8 = 0b1000_0000;
+
+/// Guards the assumption behind [`HAS_EXTENSION_FLAG`] sharing a byte with the version nibble: if
+/// `KDBUSH_VERSION` ever grows into the nibble's top bit, `header_byte` would silently set the
+/// flag on every buffer and `try_new_from_slice` would silently fold a real version ≥8 down into
+/// a smaller one, instead of either failing loudly.
+const _: () = assert!(
+    KDBUSH_VERSION < 8,
+    "KDBUSH_VERSION no longer fits below HAS_EXTENSION_FLAG in the header byte's version nibble"
+);
+
+/// Size in bytes of the optional trailer appended after the padded coords block: four
+/// little-endian `f64` bounds (min x, min y, max x, max y), a little-endian `u32` CRS/EPSG code,
+/// and 4 reserved bytes for forward-compatible extensions, kept 8-byte aligned.
+const EXTENSION_TRAILER_SIZE: usize = 4 * 8 + 4 + 4;
+
+/// Compute `(coords_byte_size, indices_byte_size)` for `num_items`, guarding against `usize`
+/// overflow instead of silently wrapping. `num_items` may come straight off an untrusted buffer
+/// (as `u32`), so on 32-bit targets `num_items * 2 * N::BYTES_PER_ELEMENT` can overflow `usize`
+/// before any length check gets a chance to reject it.
+fn checked_byte_sizes<N: IndexableNum>(num_items: usize) -> Result<(usize, usize)> {
+    let coords_byte_size = num_items
+        .checked_mul(2)
+        .and_then(|n| n.checked_mul(N::BYTES_PER_ELEMENT))
+        .ok_or_else(|| {
+            GeoIndexError::General(format!(
+                "num_items {num_items} overflows computing the coords buffer size."
+            ))
+        })?;
+    let indices_bytes_per_element = if num_items < 65536 { 2 } else { 4 };
+    let indices_byte_size = num_items
+        .checked_mul(indices_bytes_per_element)
+        .ok_or_else(|| {
+            GeoIndexError::General(format!(
+                "num_items {num_items} overflows computing the indices buffer size."
+            ))
+        })?;
+    Ok((coords_byte_size, indices_byte_size))
+}
+
+/// Sum `KDBUSH_HEADER_SIZE`, `coords_byte_size`, `indices_byte_size`, and `pad_coords_byte_size`,
+/// guarding against `usize` overflow. Each addend individually fits (per
+/// [`checked_byte_sizes`]'s own overflow checks), but their sum can still overflow `usize` on a
+/// 32-bit target, so the addition needs the same treatment as the multiplications that produced
+/// them.
+fn checked_base_buffer_length(
+    coords_byte_size: usize,
+    indices_byte_size: usize,
+    pad_coords_byte_size: usize,
+) -> Result<usize> {
+    KDBUSH_HEADER_SIZE
+        .checked_add(coords_byte_size)
+        .and_then(|n| n.checked_add(indices_byte_size))
+        .and_then(|n| n.checked_add(pad_coords_byte_size))
+        .ok_or_else(|| {
+            GeoIndexError::General(
+                "Buffer size overflows usize computing the base buffer length.".to_string(),
+            )
+        })
+}
+
+/// Bounding box and CRS/EPSG code recorded in a buffer's extension trailer.
+///
+/// The trailer always carries a bounding box; `crs` is `None` when no CRS/EPSG code was
+/// supplied. On the wire, "no CRS" is encoded as `0`, since `0` is not a valid EPSG code, so the
+/// two fields can never disagree about whether the trailer is present.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct KDTreeExtension {
+    pub(crate) bounds: [f64; 4],
+    pub(crate) crs: Option<u32>,
+}
+
 /// Common metadata to describe a KDTree
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) struct KDTreeMetadata<N: IndexableNum> {
@@ -16,32 +94,96 @@ pub(crate) struct KDTreeMetadata<N: IndexableNum> {
     pub(crate) indices_byte_size: usize,
     pub(crate) pad_coords_byte_size: usize,
     pub(crate) coords_byte_size: usize,
+    extension: Option<KDTreeExtension>,
 }
 
 impl<N: IndexableNum> KDTreeMetadata<N> {
-    pub(crate) fn new(num_items: u32, node_size: u16) -> Self {
+    pub(crate) fn new(num_items: u32, node_size: u16) -> Result<Self> {
         assert!((2..=65535).contains(&node_size));
 
         // The public API uses u32 and u16 types but internally we use usize
         let num_items = num_items as usize;
         let node_size = node_size as usize;
 
-        let coords_byte_size = num_items * 2 * N::BYTES_PER_ELEMENT;
-        let indices_bytes_per_element = if num_items < 65536 { 2 } else { 4 };
-        let indices_byte_size = num_items * indices_bytes_per_element;
+        let (coords_byte_size, indices_byte_size) = checked_byte_sizes::<N>(num_items)?;
         let pad_coords_byte_size = (8 - (indices_byte_size % 8)) % 8;
 
-        Self {
+        Ok(Self {
             node_size,
             num_items,
             phantom: PhantomData,
             indices_byte_size,
             pad_coords_byte_size,
             coords_byte_size,
-        }
+            extension: None,
+        })
+    }
+
+    /// Construct a new instance that also carries the bounding box and CRS/EPSG code recorded in
+    /// the extension trailer. Round-trips as an unextended, plain `kdbush`-compatible buffer when
+    /// `extension` is `None`.
+    pub(crate) fn new_with_extension(
+        num_items: u32,
+        node_size: u16,
+        extension: Option<KDTreeExtension>,
+    ) -> Result<Self> {
+        Ok(Self {
+            extension,
+            ..Self::new(num_items, node_size)?
+        })
+    }
+
+    /// The bounding box (min x, min y, max x, max y) recorded in the extension trailer, if this
+    /// buffer was written with one.
+    pub(crate) fn bounds(&self) -> Option<[f64; 4]> {
+        self.extension.map(|extension| extension.bounds)
+    }
+
+    /// The CRS/EPSG code recorded in the extension trailer, if this buffer was written with one.
+    pub(crate) fn crs(&self) -> Option<u32> {
+        self.extension.and_then(|extension| extension.crs)
+    }
+
+    fn has_extension(&self) -> bool {
+        self.extension.is_some()
+    }
+
+    /// The header byte encoding this metadata's version, numeric type, and whether an extension
+    /// trailer follows the padded coords block.
+    pub(crate) fn header_byte(&self) -> u8 {
+        let extension_flag = if self.has_extension() {
+            HAS_EXTENSION_FLAG
+        } else {
+            0
+        };
+        (KDBUSH_VERSION << 4) | extension_flag | N::TYPE_INDEX
+    }
+
+    /// Serializes this metadata's extension trailer, if it has one, ready to append after the
+    /// padded coords block.
+    pub(crate) fn write_extension_trailer(&self) -> Option<[u8; EXTENSION_TRAILER_SIZE]> {
+        let extension = self.extension?;
+
+        let mut trailer = [0u8; EXTENSION_TRAILER_SIZE];
+        trailer[0..8].copy_from_slice(&extension.bounds[0].to_le_bytes());
+        trailer[8..16].copy_from_slice(&extension.bounds[1].to_le_bytes());
+        trailer[16..24].copy_from_slice(&extension.bounds[2].to_le_bytes());
+        trailer[24..32].copy_from_slice(&extension.bounds[3].to_le_bytes());
+        trailer[32..36].copy_from_slice(&extension.crs.unwrap_or(0).to_le_bytes());
+        // trailer[36..40] is reserved for forward-compatible extensions and stays zeroed.
+
+        Some(trailer)
     }
 
     fn try_new_from_slice(data: &[u8]) -> Result<Self> {
+        if data.len() < KDBUSH_HEADER_SIZE {
+            return Err(GeoIndexError::General(format!(
+                "Buffer too small for a Kdbush header: expected at least {} bytes, got {}.",
+                KDBUSH_HEADER_SIZE,
+                data.len()
+            )));
+        }
+
         if data[0] != KDBUSH_MAGIC {
             return Err(GeoIndexError::General(
                 "Data not in Kdbush format.".to_string(),
@@ -49,7 +191,8 @@ impl<N: IndexableNum> KDTreeMetadata<N> {
         }
 
         let version_and_type = data[1];
-        let version = version_and_type >> 4;
+        let has_extension = version_and_type & HAS_EXTENSION_FLAG != 0;
+        let version = (version_and_type & !HAS_EXTENSION_FLAG) >> 4;
         if version != KDBUSH_VERSION {
             return Err(GeoIndexError::General(
                 format!("Got v{} data when expected v{}.", version, KDBUSH_VERSION).to_string(),
@@ -68,20 +211,65 @@ impl<N: IndexableNum> KDTreeMetadata<N> {
             ));
         }
 
-        let node_size: u16 = cast_slice(&data[2..4])[0];
-        let num_items: u32 = cast_slice(&data[4..8])[0];
+        let node_size: u16 = try_cast_slice::<_, u16>(&data[2..4]).map_err(|_| {
+            GeoIndexError::General("Buffer is not aligned to read node_size.".to_string())
+        })?[0];
+        let num_items: u32 = try_cast_slice::<_, u32>(&data[4..8]).map_err(|_| {
+            GeoIndexError::General("Buffer is not aligned to read num_items.".to_string())
+        })?[0];
 
         let node_size = node_size as usize;
         let num_items = num_items as usize;
 
-        let coords_byte_size = num_items * 2 * N::BYTES_PER_ELEMENT;
-        let indices_bytes_per_element = if num_items < 65536 { 2 } else { 4 };
-        let indices_byte_size = num_items * indices_bytes_per_element;
+        let (coords_byte_size, indices_byte_size) = checked_byte_sizes::<N>(num_items)?;
         let pad_coords_byte_size = (8 - (indices_byte_size % 8)) % 8;
 
-        let data_buffer_length =
-            KDBUSH_HEADER_SIZE + coords_byte_size + indices_byte_size + pad_coords_byte_size;
-        assert_eq!(data.len(), data_buffer_length);
+        let base_buffer_length =
+            checked_base_buffer_length(coords_byte_size, indices_byte_size, pad_coords_byte_size)?;
+        let data_buffer_length = if has_extension {
+            base_buffer_length
+                .checked_add(EXTENSION_TRAILER_SIZE)
+                .ok_or_else(|| {
+                    GeoIndexError::General(
+                        "Buffer size overflows usize computing the extended buffer length."
+                            .to_string(),
+                    )
+                })?
+        } else {
+            base_buffer_length
+        };
+        match data.len().cmp(&data_buffer_length) {
+            Ordering::Less => {
+                return Err(GeoIndexError::General(format!(
+                    "Buffer too small for Kdbush data: expected {} bytes, got {}.",
+                    data_buffer_length,
+                    data.len()
+                )));
+            }
+            Ordering::Greater => {
+                return Err(GeoIndexError::General(format!(
+                    "Buffer has {} trailing byte(s) after Kdbush data; expected exactly {} bytes.",
+                    data.len() - data_buffer_length,
+                    data_buffer_length
+                )));
+            }
+            Ordering::Equal => {}
+        }
+
+        let extension = if has_extension {
+            let trailer = &data[base_buffer_length..data_buffer_length];
+            let min_x = f64::from_le_bytes(trailer[0..8].try_into().unwrap());
+            let min_y = f64::from_le_bytes(trailer[8..16].try_into().unwrap());
+            let max_x = f64::from_le_bytes(trailer[16..24].try_into().unwrap());
+            let max_y = f64::from_le_bytes(trailer[24..32].try_into().unwrap());
+            let crs = u32::from_le_bytes(trailer[32..36].try_into().unwrap());
+            Some(KDTreeExtension {
+                bounds: [min_x, min_y, max_x, max_y],
+                crs: (crs != 0).then_some(crs),
+            })
+        } else {
+            None
+        };
 
         Ok(Self {
             node_size,
@@ -90,33 +278,71 @@ impl<N: IndexableNum> KDTreeMetadata<N> {
             indices_byte_size,
             pad_coords_byte_size,
             coords_byte_size,
+            extension,
         })
     }
 
-    pub(crate) fn data_buffer_length(&self) -> usize {
-        KDBUSH_HEADER_SIZE
-            + self.coords_byte_size
-            + self.indices_byte_size
-            + self.pad_coords_byte_size
+    pub(crate) fn data_buffer_length(&self) -> Result<usize> {
+        let base_buffer_length = checked_base_buffer_length(
+            self.coords_byte_size,
+            self.indices_byte_size,
+            self.pad_coords_byte_size,
+        )?;
+        if self.has_extension() {
+            base_buffer_length
+                .checked_add(EXTENSION_TRAILER_SIZE)
+                .ok_or_else(|| {
+                    GeoIndexError::General(
+                        "Buffer size overflows usize computing the extended buffer length."
+                            .to_string(),
+                    )
+                })
+        } else {
+            Ok(base_buffer_length)
+        }
     }
 
-    pub(crate) fn coords_slice<'a>(&self, data: &'a [u8]) -> &'a [N] {
-        let coords_byte_start =
-            KDBUSH_HEADER_SIZE + self.indices_byte_size + self.pad_coords_byte_size;
-        let coords_byte_end = KDBUSH_HEADER_SIZE
-            + self.indices_byte_size
-            + self.pad_coords_byte_size
-            + self.coords_byte_size;
-        cast_slice(&data[coords_byte_start..coords_byte_end])
+    pub(crate) fn coords_slice<'a>(&self, data: &'a [u8]) -> Result<&'a [N]> {
+        let coords_byte_start = KDBUSH_HEADER_SIZE
+            .checked_add(self.indices_byte_size)
+            .and_then(|n| n.checked_add(self.pad_coords_byte_size))
+            .ok_or_else(|| {
+                GeoIndexError::General(
+                    "Buffer size overflows usize computing the coords start offset.".to_string(),
+                )
+            })?;
+        let coords_byte_end = coords_byte_start
+            .checked_add(self.coords_byte_size)
+            .ok_or_else(|| {
+                GeoIndexError::General(
+                    "Buffer size overflows usize computing the coords end offset.".to_string(),
+                )
+            })?;
+        let buf = data
+            .get(coords_byte_start..coords_byte_end)
+            .ok_or_else(|| {
+                GeoIndexError::General("Buffer too small to contain coords.".to_string())
+            })?;
+        try_cast_slice(buf).map_err(|_| {
+            GeoIndexError::General("Buffer is not aligned to read coords.".to_string())
+        })
     }
 
-    pub(crate) fn indices_slice<'a>(&self, data: &'a [u8]) -> Indices<'a> {
-        let indices_buf = &data[KDBUSH_HEADER_SIZE..KDBUSH_HEADER_SIZE + self.indices_byte_size];
+    pub(crate) fn indices_slice<'a>(&self, data: &'a [u8]) -> Result<Indices<'a>> {
+        let indices_buf = data
+            .get(KDBUSH_HEADER_SIZE..KDBUSH_HEADER_SIZE + self.indices_byte_size)
+            .ok_or_else(|| {
+                GeoIndexError::General("Buffer too small to contain indices.".to_string())
+            })?;
 
         if self.num_items < 65536 {
-            Indices::U16(cast_slice(indices_buf))
+            Ok(Indices::U16(try_cast_slice(indices_buf).map_err(|_| {
+                GeoIndexError::General("Buffer is not aligned to read indices.".to_string())
+            })?))
         } else {
-            Indices::U32(cast_slice(indices_buf))
+            Ok(Indices::U32(try_cast_slice(indices_buf).map_err(|_| {
+                GeoIndexError::General("Buffer is not aligned to read indices.".to_string())
+            })?))
         }
     }
 }
@@ -131,10 +357,61 @@ pub struct OwnedKDTree<N: IndexableNum> {
 }
 
 impl<N: IndexableNum> OwnedKDTree<N> {
+    /// Construct an owned buffer directly from an already kd-tree-sorted `coords`/`indices`
+    /// pair, optionally recording a bounds/CRS extension trailer.
+    ///
+    /// This is a low-level constructor that only serializes the kdbush ABI; most callers should
+    /// go through [`KDTreeBuilder`][crate::kdtree::KDTreeBuilder] instead, which also computes
+    /// the kd-tree ordering from raw points.
+    pub(crate) fn from_sorted_parts(
+        coords: &[N],
+        indices: Indices<'_>,
+        node_size: u16,
+        extension: Option<KDTreeExtension>,
+    ) -> Result<Self> {
+        let num_items = (coords.len() / 2) as u32;
+        let metadata = KDTreeMetadata::new_with_extension(num_items, node_size, extension)?;
+
+        let mut buffer = Vec::with_capacity(metadata.data_buffer_length()?);
+        buffer.push(KDBUSH_MAGIC);
+        buffer.push(metadata.header_byte());
+        buffer.extend_from_slice(&node_size.to_le_bytes());
+        buffer.extend_from_slice(&num_items.to_le_bytes());
+
+        match indices {
+            Indices::U16(indices) => buffer.extend_from_slice(cast_slice(indices)),
+            Indices::U32(indices) => buffer.extend_from_slice(cast_slice(indices)),
+        }
+        buffer.resize(
+            KDBUSH_HEADER_SIZE + metadata.indices_byte_size + metadata.pad_coords_byte_size,
+            0,
+        );
+        buffer.extend_from_slice(cast_slice(coords));
+
+        if let Some(trailer) = metadata.write_extension_trailer() {
+            buffer.extend_from_slice(&trailer);
+        }
+
+        debug_assert_eq!(buffer.len(), metadata.data_buffer_length().unwrap());
+
+        Ok(Self { buffer, metadata })
+    }
+
     /// Consume this KDTree, returning the underlying buffer.
     pub fn into_inner(self) -> Vec<u8> {
         self.buffer
     }
+
+    /// The bounding box (min x, min y, max x, max y) recorded in this buffer's extension
+    /// trailer, if it has one.
+    pub fn bounds(&self) -> Option<[f64; 4]> {
+        self.metadata.bounds()
+    }
+
+    /// The CRS/EPSG code recorded in this buffer's extension trailer, if it has one.
+    pub fn crs(&self) -> Option<u32> {
+        self.metadata.crs()
+    }
 }
 
 impl<N: IndexableNum> AsRef<[u8]> for OwnedKDTree<N> {
@@ -165,8 +442,8 @@ impl<'a, N: IndexableNum> KDTreeRef<'a, N> {
     pub fn try_new<T: AsRef<[u8]>>(data: &'a T) -> Result<Self> {
         let data = data.as_ref();
         let metadata = KDTreeMetadata::try_new_from_slice(data)?;
-        let coords = metadata.coords_slice(data);
-        let indices = metadata.indices_slice(data);
+        let coords = metadata.coords_slice(data)?;
+        let indices = metadata.indices_slice(data)?;
 
         Ok(Self {
             coords,
@@ -174,4 +451,235 @@ impl<'a, N: IndexableNum> KDTreeRef<'a, N> {
             metadata,
         })
     }
+
+    /// The bounding box (min x, min y, max x, max y) recorded in this buffer's extension
+    /// trailer, if it has one.
+    pub fn bounds(&self) -> Option<[f64; 4]> {
+        self.metadata.bounds()
+    }
+
+    /// The CRS/EPSG code recorded in this buffer's extension trailer, if it has one.
+    pub fn crs(&self) -> Option<u32> {
+        self.metadata.crs()
+    }
+
+    /// Find the indices of all points whose coordinates fall within the given bounding box
+    /// (inclusive on every side), searching via the buffer's node-size-bounded kd-tree layout.
+    pub fn range(&self, min_x: N, min_y: N, max_x: N, max_y: N) -> Vec<u32>
+    where
+        N: PartialOrd,
+    {
+        let mut result = Vec::new();
+        if self.metadata.num_items == 0 {
+            return result;
+        }
+
+        let mut stack = vec![(0usize, self.metadata.num_items - 1, 0usize)];
+        while let Some((left, right, axis)) = stack.pop() {
+            if right - left <= self.metadata.node_size {
+                for i in left..=right {
+                    let x = self.coords[2 * i];
+                    let y = self.coords[2 * i + 1];
+                    if x >= min_x && x <= max_x && y >= min_y && y <= max_y {
+                        result.push(self.index_at(i));
+                    }
+                }
+                continue;
+            }
+
+            let m = (left + right) / 2;
+            let x = self.coords[2 * m];
+            let y = self.coords[2 * m + 1];
+            if x >= min_x && x <= max_x && y >= min_y && y <= max_y {
+                result.push(self.index_at(m));
+            }
+
+            let next_axis = 1 - axis;
+            let (below, above) = if axis == 0 {
+                (min_x <= x, max_x >= x)
+            } else {
+                (min_y <= y, max_y >= y)
+            };
+            if below && m > left {
+                stack.push((left, m - 1, next_axis));
+            }
+            if above {
+                stack.push((m + 1, right, next_axis));
+            }
+        }
+
+        result
+    }
+
+    fn index_at(&self, i: usize) -> u32 {
+        match &self.indices {
+            Indices::U16(indices) => indices[i] as u32,
+            Indices::U32(indices) => indices[i],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owned_kdtree(extension: Option<KDTreeExtension>) -> OwnedKDTree<f64> {
+        let coords: Vec<f64> = vec![0.0, 0.0, 1.0, 1.0];
+        let indices: Vec<u16> = vec![0, 1];
+        OwnedKDTree::from_sorted_parts(&coords, Indices::U16(&indices), 2, extension).unwrap()
+    }
+
+    #[test]
+    fn try_new_from_slice_rejects_buffer_smaller_than_header() {
+        let err = KDTreeRef::<f64>::try_new(&vec![KDBUSH_MAGIC, 0]).unwrap_err();
+        assert!(matches!(err, GeoIndexError::General(_)));
+    }
+
+    #[test]
+    fn try_new_from_slice_rejects_bad_magic() {
+        let mut buf = owned_kdtree(None).into_inner();
+        buf[0] = KDBUSH_MAGIC.wrapping_add(1);
+        assert!(KDTreeRef::<f64>::try_new(&buf).is_err());
+    }
+
+    #[test]
+    fn try_new_from_slice_rejects_bad_version() {
+        let mut buf = owned_kdtree(None).into_inner();
+        // Keep the type nibble, bump the version nibble past what we wrote.
+        buf[1] = ((KDBUSH_VERSION + 1) << 4) | (buf[1] & 0x0f);
+        assert!(KDTreeRef::<f64>::try_new(&buf).is_err());
+    }
+
+    #[test]
+    fn try_new_from_slice_rejects_bad_type() {
+        let mut buf = owned_kdtree(None).into_inner();
+        buf[1] = (buf[1] & !0x0f) | 0x0f;
+        assert!(KDTreeRef::<f64>::try_new(&buf).is_err());
+    }
+
+    #[test]
+    fn try_new_from_slice_rejects_truncated_buffer() {
+        let mut buf = owned_kdtree(None).into_inner();
+        buf.pop();
+        let err = KDTreeRef::<f64>::try_new(&buf).unwrap_err();
+        assert!(matches!(err, GeoIndexError::General(_)));
+    }
+
+    #[test]
+    fn try_new_from_slice_rejects_trailing_garbage() {
+        let mut buf = owned_kdtree(None).into_inner();
+        buf.push(0);
+        let err = KDTreeRef::<f64>::try_new(&buf).unwrap_err();
+        assert!(matches!(err, GeoIndexError::General(_)));
+    }
+
+    #[test]
+    fn classic_buffer_round_trips_without_a_trailer() {
+        let owned = owned_kdtree(None);
+        assert_eq!(owned.buffer.len(), owned.metadata.data_buffer_length().unwrap());
+
+        let tree_ref = KDTreeRef::<f64>::try_new(&owned).unwrap();
+        assert_eq!(tree_ref.bounds(), None);
+        assert_eq!(tree_ref.crs(), None);
+    }
+
+    #[test]
+    fn extended_buffer_round_trips_bounds_and_crs() {
+        let extension = KDTreeExtension {
+            bounds: [0.0, 0.0, 1.0, 1.0],
+            crs: Some(4326),
+        };
+        let owned = owned_kdtree(Some(extension));
+        assert_eq!(owned.buffer.len(), owned.metadata.data_buffer_length().unwrap());
+
+        let tree_ref = KDTreeRef::<f64>::try_new(&owned).unwrap();
+        assert_eq!(tree_ref.bounds(), Some([0.0, 0.0, 1.0, 1.0]));
+        assert_eq!(tree_ref.crs(), Some(4326));
+    }
+
+    #[test]
+    fn extended_buffer_without_crs_round_trips_to_none() {
+        let extension = KDTreeExtension {
+            bounds: [0.0, 0.0, 1.0, 1.0],
+            crs: None,
+        };
+        let owned = owned_kdtree(Some(extension));
+
+        let tree_ref = KDTreeRef::<f64>::try_new(&owned).unwrap();
+        assert_eq!(tree_ref.bounds(), Some([0.0, 0.0, 1.0, 1.0]));
+        assert_eq!(tree_ref.crs(), None);
+    }
+
+    fn owned_kdtree_with_points(points: &[(f64, f64)], node_size: u16) -> OwnedKDTree<f64> {
+        let mut coords = Vec::with_capacity(points.len() * 2);
+        for &(x, y) in points {
+            coords.push(x);
+            coords.push(y);
+        }
+        let indices: Vec<u16> = (0..points.len() as u16).collect();
+        OwnedKDTree::from_sorted_parts(&coords, Indices::U16(&indices), node_size, None).unwrap()
+    }
+
+    #[test]
+    fn range_finds_only_points_within_the_box() {
+        let points = [
+            (0.0, 0.0),
+            (10.0, 10.0),
+            (5.0, 5.0),
+            (2.0, 8.0),
+            (8.0, 2.0),
+            (-1.0, -1.0),
+        ];
+        let owned = owned_kdtree_with_points(&points, 2);
+        let tree_ref = KDTreeRef::<f64>::try_new(&owned).unwrap();
+
+        let mut found = tree_ref.range(0.0, 0.0, 5.0, 5.0);
+        found.sort_unstable();
+        assert_eq!(found, vec![0, 2]);
+    }
+
+    #[test]
+    fn range_over_the_full_bounds_finds_every_point() {
+        let points = [(0.0, 0.0), (1.0, 1.0), (2.0, 2.0), (3.0, 3.0), (4.0, 4.0)];
+        let owned = owned_kdtree_with_points(&points, 2);
+        let tree_ref = KDTreeRef::<f64>::try_new(&owned).unwrap();
+
+        let mut found = tree_ref.range(0.0, 0.0, 4.0, 4.0);
+        found.sort_unstable();
+        assert_eq!(found, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn range_outside_every_point_finds_nothing() {
+        let points = [(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)];
+        let owned = owned_kdtree_with_points(&points, 2);
+        let tree_ref = KDTreeRef::<f64>::try_new(&owned).unwrap();
+
+        assert!(tree_ref.range(100.0, 100.0, 200.0, 200.0).is_empty());
+    }
+
+    #[test]
+    fn checked_byte_sizes_returns_an_error_instead_of_overflowing() {
+        assert!(checked_byte_sizes::<f64>(usize::MAX).is_err());
+    }
+
+    #[test]
+    fn checked_byte_sizes_computes_sizes_for_ordinary_counts() {
+        assert_eq!(checked_byte_sizes::<f64>(4).unwrap(), (64, 8));
+    }
+
+    #[test]
+    fn checked_base_buffer_length_returns_an_error_instead_of_overflowing() {
+        // Each addend individually fits in a `usize`, but none of `checked_byte_sizes`'s
+        // multiplication checks catch their sum overflowing.
+        assert!(checked_base_buffer_length(usize::MAX / 2, usize::MAX / 2, 7).is_err());
+    }
+
+    #[test]
+    fn checked_base_buffer_length_computes_lengths_for_ordinary_sizes() {
+        assert_eq!(
+            checked_base_buffer_length(64, 8, 0).unwrap(),
+            KDBUSH_HEADER_SIZE + 72
+        );
+    }
 }
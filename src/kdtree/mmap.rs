@@ -0,0 +1,158 @@
+use std::fs::File;
+use std::marker::PhantomData;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::error::{GeoIndexError, Result};
+use crate::kdtree::index::KDTreeRef;
+use crate::r#type::IndexableNum;
+
+/// A read-only, memory-mapped KDTree buffer.
+///
+/// This maps a kdbush-formatted file from disk without copying its coords and indices arrays
+/// into memory, which is useful for huge prebuilt indices (generated elsewhere, including by the
+/// JavaScript `kdbush` library) that should be queried lazily. The buffer is validated at
+/// [`open`][Self::open] time, using the same checks as [`KDTreeRef::try_new`].
+#[derive(Debug)]
+pub struct MmapKDTree<N: IndexableNum> {
+    mmap: Mmap,
+    phantom: PhantomData<N>,
+}
+
+impl<N: IndexableNum> MmapKDTree<N> {
+    /// Open a kdbush-formatted file as a read-only memory map and validate its contents.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path.as_ref()).map_err(|err| {
+            GeoIndexError::General(format!("Failed to open {}: {err}", path.as_ref().display()))
+        })?;
+
+        // Safety: the caller must not mutate or truncate the underlying file for as long as this
+        // mapping is alive. Doing so is undefined behavior, per the `memmap2` documentation.
+        let mmap = unsafe { Mmap::map(&file) }.map_err(|err| {
+            GeoIndexError::General(format!("Failed to mmap {}: {err}", path.as_ref().display()))
+        })?;
+
+        // Validate eagerly so `open` fails up front instead of on first query.
+        KDTreeRef::<N>::try_new(&mmap)?;
+
+        Ok(Self {
+            mmap,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Borrow a [`KDTreeRef`] over the memory-mapped buffer.
+    ///
+    /// This re-runs [`KDTreeRef::try_new`] over the mapping, which already passed those checks in
+    /// [`open`][Self::open], so it does not fail.
+    pub fn as_kdtree_ref(&self) -> KDTreeRef<'_, N> {
+        KDTreeRef::try_new(self).expect("buffer was already validated in `MmapKDTree::open`")
+    }
+}
+
+impl<N: IndexableNum> AsRef<[u8]> for MmapKDTree<N> {
+    fn as_ref(&self) -> &[u8] {
+        &self.mmap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::indices::Indices;
+    use crate::kdtree::builder::KDTreeBuilder;
+    use crate::kdtree::index::{KDTreeExtension, OwnedKDTree};
+
+    fn write_temp_bytes(name: &str, data: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "geo-index-mmap-test-{}-{}-{:p}.bin",
+            std::process::id(),
+            name,
+            data
+        ));
+        let mut file = File::create(&path).unwrap();
+        file.write_all(data).unwrap();
+        path
+    }
+
+    fn write_temp_kdtree(extension: Option<KDTreeExtension>) -> std::path::PathBuf {
+        let coords: Vec<f64> = vec![0.0, 0.0, 1.0, 1.0];
+        let indices: Vec<u16> = vec![0, 1];
+        let owned =
+            OwnedKDTree::from_sorted_parts(&coords, Indices::U16(&indices), 2, extension).unwrap();
+        write_temp_bytes("valid", owned.into_inner().as_slice())
+    }
+
+    #[test]
+    fn open_rejects_a_missing_file() {
+        let path = std::env::temp_dir().join("geo-index-mmap-test-does-not-exist.bin");
+        assert!(MmapKDTree::<f64>::open(path).is_err());
+    }
+
+    #[test]
+    fn open_rejects_a_file_that_maps_fine_but_fails_validation() {
+        let coords: Vec<f64> = vec![0.0, 0.0, 1.0, 1.0];
+        let indices: Vec<u16> = vec![0, 1];
+        let owned =
+            OwnedKDTree::from_sorted_parts(&coords, Indices::U16(&indices), 2, None).unwrap();
+        let mut bytes = owned.into_inner();
+        bytes.truncate(bytes.len() - 1);
+        let path = write_temp_bytes("truncated", &bytes);
+
+        let err = MmapKDTree::<f64>::open(&path).unwrap_err();
+        assert!(matches!(err, GeoIndexError::General(_)));
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn open_and_query_round_trips_the_extension() {
+        let extension = KDTreeExtension {
+            bounds: [0.0, 0.0, 1.0, 1.0],
+            crs: Some(4326),
+        };
+        let path = write_temp_kdtree(Some(extension));
+
+        let tree = MmapKDTree::<f64>::open(&path).unwrap();
+        let tree_ref = tree.as_kdtree_ref();
+        assert_eq!(tree_ref.bounds(), Some([0.0, 0.0, 1.0, 1.0]));
+        assert_eq!(tree_ref.crs(), Some(4326));
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn open_a_tree_spanning_multiple_pages_and_reopening_it_gives_the_same_answer() {
+        // 5,000 points comfortably exceeds a single 4 KiB page, so this exercises random-offset
+        // reads into the mapping across page boundaries, not just a buffer small enough to sit
+        // entirely in one page. Opening the file twice (rather than reusing one `MmapKDTree`)
+        // also covers that a freshly re-mapped reader of the same persisted file sees the same
+        // data, which is the scenario this type exists for: a large prebuilt index read back
+        // from disk, possibly by a different process than the one that wrote it.
+        let mut builder = KDTreeBuilder::<f64>::new();
+        let num_points = 5_000u32;
+        for i in 0..num_points {
+            builder.add(i as f64, (i * 2) as f64);
+        }
+        let owned = builder.finish().unwrap();
+        let path = write_temp_bytes("large", owned.into_inner().as_slice());
+
+        let expected: Vec<u32> = (0..=10).collect();
+
+        let first = MmapKDTree::<f64>::open(&path).unwrap();
+        let mut found = first.as_kdtree_ref().range(0.0, 0.0, 10.0, 20.0);
+        found.sort_unstable();
+        assert_eq!(found, expected);
+        drop(first);
+
+        let reopened = MmapKDTree::<f64>::open(&path).unwrap();
+        let mut found_again = reopened.as_kdtree_ref().range(0.0, 0.0, 10.0, 20.0);
+        found_again.sort_unstable();
+        assert_eq!(found_again, expected);
+
+        std::fs::remove_file(path).ok();
+    }
+}
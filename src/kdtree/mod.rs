@@ -0,0 +1,10 @@
+mod builder;
+mod constants;
+mod index;
+#[cfg(feature = "mmap")]
+mod mmap;
+
+pub use builder::KDTreeBuilder;
+pub use index::{KDTreeRef, OwnedKDTree};
+#[cfg(feature = "mmap")]
+pub use mmap::MmapKDTree;